@@ -15,16 +15,117 @@ use std::{
 
 use anyhow::Context;
 
+/// A lint level, i.e. how a lint is reported to the user
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Level {
+	Warn,
+	Deny,
+	Allow,
+	Forbid,
+}
+
+impl Level {
+	/// Priority lints at this level default to, matching the previous fixed
+	/// `warn` -> `deny` -> `allow` order (with `forbid` emitted last, as it's
+	/// the strictest level)
+	fn default_priority(self) -> i32 {
+		match self {
+			Self::Warn => 0,
+			Self::Deny => 1,
+			Self::Allow => 2,
+			Self::Forbid => 3,
+		}
+	}
+
+	/// Returns the clippy/rustc flag used to set a lint to this level
+	fn flag(self) -> &'static str {
+		match self {
+			Self::Warn => "-W",
+			Self::Deny => "-D",
+			Self::Allow => "-A",
+			Self::Forbid => "-F",
+		}
+	}
+}
+
+/// A single lint entry
+///
+/// May either be a bare lint name (which may also be a `@group_name`
+/// reference, expanded via `[groups]`), or a table specifying an explicit
+/// `priority`, used to control precedence against lints declared at other
+/// levels, and/or a `reason`, documenting why the lint was set.
+#[derive(Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum LintEntry {
+	/// A bare lint name, or `@group_name` reference
+	Name(String),
+
+	/// A lint with an explicit priority and/or reason
+	Full {
+		/// Lint name, or `@group_name` reference
+		lint:     String,
+		/// Priority, used to stably sort the emitted flags across all levels
+		#[serde(default)]
+		priority: Option<i32>,
+		/// Reason this lint was set, echoed in the "Running cargo" banner
+		#[serde(default)]
+		reason:   Option<String>,
+	},
+}
+
+impl LintEntry {
+	/// Returns this entry's lint name, or `@group_name` reference
+	fn name(&self) -> &str {
+		match self {
+			Self::Name(name) => name,
+			Self::Full { lint, .. } => lint,
+		}
+	}
+
+	/// Returns this entry's priority, falling back to `level`'s default
+	fn priority(&self, level: Level) -> i32 {
+		match self {
+			Self::Name(_) => level.default_priority(),
+			Self::Full { priority, .. } => priority.unwrap_or_else(|| level.default_priority()),
+		}
+	}
+
+	/// Returns this entry's reason, if any
+	fn reason(&self) -> Option<&str> {
+		match self {
+			Self::Name(_) => None,
+			Self::Full { reason, .. } => reason.as_deref(),
+		}
+	}
+}
+
+/// Returns the default for the `inherit` key, used when it's missing
+fn default_inherit() -> bool {
+	true
+}
+
 /// All lints defined in file
 #[derive(Clone, Default, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
 struct Lints {
 	#[serde(default)]
-	deny:  Vec<String>,
+	deny:    Vec<LintEntry>,
+	#[serde(default)]
+	allow:   Vec<LintEntry>,
 	#[serde(default)]
-	allow: Vec<String>,
+	warn:    Vec<LintEntry>,
 	#[serde(default)]
-	warn:  Vec<String>,
+	forbid:  Vec<LintEntry>,
+	/// Named groups of lints, referenced from the level lists as `@group_name`
+	#[serde(default)]
+	groups:  std::collections::HashMap<String, Vec<String>>,
+	/// Whether configs found further up the directory hierarchy should also
+	/// be merged in, when parsed through `from_config_merged`
+	#[serde(default = "default_inherit")]
+	inherit: bool,
 }
 
 impl Lints {
@@ -33,81 +134,174 @@ impl Lints {
 }
 
 impl Lints {
-	/// Finds the config path in the current directory or any
-	/// parent directory
-	pub fn find_config_path() -> Result<Option<PathBuf>, anyhow::Error> {
+	/// Parses the lints from a path
+	pub fn from_config_with_path(path: &Path) -> Result<Self, anyhow::Error> {
+		fs::read_to_string(path)
+			.context("Failed to read config")
+			.map(|s| toml::from_str(&s))?
+			.context("Failed to parse config")
+	}
+
+	/// Finds and parses every config from the current directory up to the
+	/// root, stopping early (but inclusively) at the first config with
+	/// `inherit = false`
+	///
+	/// Returns the configs ordered from furthest to closest, i.e. the order
+	/// they should be merged in so that closer configs win.
+	pub fn find_config_paths() -> Result<Vec<(PathBuf, Self)>, anyhow::Error> {
 		// Get the current path to start looking
 		let mut cur_path = env::current_dir().context("Failed to get current directory")?;
 
-		// Then keep ascending until we find it
+		// Then keep ascending, gathering every config we find
+		let mut configs = vec![];
 		loop {
 			// Get the path
 			let lints_path = cur_path.join(Lints::FILE_NAME);
 
 			// Then check if it exists
-			match lints_path.exists() {
-				// If it did, return it
-				true => break Ok(Some(lints_path)),
-
-				// Else check if we still have a parent
-				false => match cur_path.parent() {
-					// If so, retry
-					Some(parent) => cur_path = parent.to_path_buf(),
-					// Else return `None`
-					None => return Ok(None),
-				},
+			if lints_path.exists() {
+				let lints = Self::from_config_with_path(&lints_path)?;
+				let inherit = lints.inherit;
+				configs.push((lints_path, lints));
+
+				// If it opted out of inheriting, stop ascending
+				if !inherit {
+					break;
+				}
+			}
+
+			// Then check if we still have a parent
+			match cur_path.parent() {
+				// If so, keep ascending
+				Some(parent) => cur_path = parent.to_path_buf(),
+				// Else we're done
+				None => break,
 			}
 		}
+
+		// We gathered configs from closest to furthest, but want to merge furthest-first
+		configs.reverse();
+		Ok(configs)
 	}
 
-	/// Parses the lints from config
-	pub fn from_config() -> Result<Self, anyhow::Error> {
-		Self::find_config_path()?.map_or_else(|| Ok(Lints::default()), |path| Self::from_config_with_path(&path))
+	/// Parses and merges the lints from every config found from the root down
+	/// to the current directory, see `find_config_paths`
+	pub fn from_config_merged() -> Result<Self, anyhow::Error> {
+		let mut lints = Lints::default();
+		for (_, config) in Self::find_config_paths()? {
+			lints.merge(config);
+		}
+
+		Ok(lints)
 	}
 
-	/// Parses the lints from a path
-	pub fn from_config_with_path(path: &Path) -> Result<Self, anyhow::Error> {
-		fs::read_to_string(path)
-			.context("Failed to read config")
-			.map(|s| toml::from_str(&s))?
-			.context("Failed to parse config")
+	/// Merges `other`'s lints into `self`, with `other`'s appended last so
+	/// they take precedence over `self`'s entries of the same priority under
+	/// `all_flags`'s stable priority sort. Entries of a higher priority still
+	/// win, whichever of the two declared them.
+	///
+	/// Groups are merged by name, with `other`'s groups overriding `self`'s
+	/// on conflict.
+	pub fn merge(&mut self, other: Lints) {
+		self.deny.extend(other.deny);
+		self.allow.extend(other.allow);
+		self.warn.extend(other.warn);
+		self.forbid.extend(other.forbid);
+		self.groups.extend(other.groups);
 	}
 
-	/// Constructs all deny flags
-	fn deny_flags(&self) -> Vec<String> {
-		self.deny
-			.iter()
-			.flat_map(|lint| vec!["-D".to_owned(), lint.clone()].into_iter())
-			.collect()
+	/// Expands `entry` into its `(priority, level, lint name)` tuples,
+	/// resolving `@group_name` references against `[groups]`
+	///
+	/// Groups may not reference other groups: a `@`-prefixed lint found
+	/// inside a group's own list is rejected, rather than silently forwarded
+	/// to `clippy` as a literal (broken) lint name.
+	fn expand_entry<'a>(&'a self, level: Level, entry: &'a LintEntry) -> Result<Vec<(i32, Level, &'a str)>, anyhow::Error> {
+		match entry.name().strip_prefix('@') {
+			Some(group) => self
+				.groups
+				.get(group)
+				.with_context(|| format!("Unknown lint group {group:?}"))?
+				.iter()
+				.map(|lint| match lint.strip_prefix('@') {
+					Some(_) => anyhow::bail!("Group {group:?} references another group ({lint:?}), which isn't supported"),
+					None => Ok((entry.priority(level), level, lint.as_str())),
+				})
+				.collect(),
+			None => Ok(vec![(entry.priority(level), level, entry.name())]),
+		}
 	}
 
-	/// Constructs all warn flags
-	fn warn_flags(&self) -> Vec<String> {
-		self.warn
-			.iter()
-			.flat_map(|lint| vec!["-W".to_owned(), lint.clone()].into_iter())
+	/// Returns the `(lint, reason)` pairs of every entry that declared a reason
+	fn reasons(&self) -> Vec<(&str, &str)> {
+		[&self.warn, &self.deny, &self.allow, &self.forbid]
+			.into_iter()
+			.flatten()
+			.filter_map(|lint| lint.reason().map(|reason| (lint.name(), reason)))
 			.collect()
 	}
 
-	/// Constructs all allow flags
-	fn allow_flags(&self) -> Vec<String> {
-		self.allow
-			.iter()
-			.flat_map(|lint| vec!["-A".to_owned(), lint.clone()].into_iter())
-			.collect()
+	/// Constructs all flags, in declaration-priority order
+	///
+	/// Lints are sorted (stably) by priority across all levels, so that an
+	/// `allow` declared with a higher priority than a conflicting `deny` wins,
+	/// regardless of which level's list it was declared in. Any `@group_name`
+	/// entries are expanded via `[groups]` first.
+	fn all_flags(&self) -> Result<Vec<String>, anyhow::Error> {
+		let mut entries = vec![];
+		for (level, lints) in [
+			(Level::Warn, &self.warn),
+			(Level::Deny, &self.deny),
+			(Level::Allow, &self.allow),
+			(Level::Forbid, &self.forbid),
+		] {
+			for lint in lints {
+				entries.extend(self.expand_entry(level, lint)?);
+			}
+		}
+
+		entries.sort_by_key(|&(priority, ..)| priority);
+
+		Ok(entries
+			.into_iter()
+			.flat_map(|(_, level, name)| [level.flag().to_owned(), name.to_owned()])
+			.collect())
+	}
+
+	/// Splits `args` at the first `--` separator into cargo-check args (before)
+	/// and post-`--` clippy args (after), modeled after clippy driver's
+	/// `arg_value` argument scanning
+	fn split_args(args: impl IntoIterator<Item = OsString>) -> (Vec<OsString>, Vec<OsString>) {
+		let mut args = args.into_iter();
+
+		let mut check_args = vec![];
+		for arg in &mut args {
+			if arg == "--" {
+				break;
+			}
+			check_args.push(arg);
+		}
+
+		(check_args, args.collect())
 	}
 
 	/// Runs clippy with `args`
 	pub fn run_clippy(&self, args: impl IntoIterator<Item = OsString>) -> Result<ExitStatus, anyhow::Error> {
+		// Split our own args from any the user already passed to clippy, so we
+		// only ever emit a single `--` separator
+		let (check_args, clippy_args) = Self::split_args(args);
+
 		// Build the command
+		// Note: Lint reasons have no rustc/clippy CLI flag equivalent, only the
+		// `lint_reasons` attribute form (`#[allow(lint, reason = "...")]`), so we
+		// can't forward them as flags. We echo them below instead.
 		let mut cmd = Command::new("cargo");
 		let cmd = cmd
 			.arg("clippy")
-			.args(args)
+			.args(check_args)
 			.arg("--")
-			.args(self.warn_flags())
-			.args(self.deny_flags())
-			.args(self.allow_flags());
+			.args(clippy_args)
+			.args(self.all_flags()?);
 
 		// Print what we're running
 		eprint!("Running \"cargo\"");
@@ -116,6 +310,11 @@ impl Lints {
 		}
 		eprintln!();
 
+		// Then print why each lint with a reason was set
+		for (lint, reason) in self.reasons() {
+			eprintln!("note: `{lint}` set due to: {reason}");
+		}
+
 		// Spawn it and wait
 		cmd.spawn()
 			.context("Unable to start clippy")?
@@ -124,22 +323,202 @@ impl Lints {
 	}
 }
 
-fn main() -> Result<(), anyhow::Error> {
-	// Get the lints
-	let lints = Lints::from_config()?;
+/// Prints the help text, describing the `lints.toml` mechanism
+fn print_help() {
+	println!(
+		"cargo-clippy-lints {}
+Cargo subcommand to run `clippy` with external lints defined in a `lints.toml`
 
-	// Then run clippy
+USAGE:
+    cargo clippy-lints [OPTIONS]
+
+OPTIONS:
+    -h, --help       Prints this help and exits
+    -V, --version    Prints version information and exits
+
+    All other options are forwarded to `cargo clippy`. Put a `--` before
+    options meant for `clippy` itself, such as lint flags.
+
+CONFIGURATION:
+    On startup, every `lints.toml` found while ascending from the current
+    directory up to the root is parsed and merged, so a crate-local config
+    can extend a workspace-root config. Entries of closer (more specific)
+    configs are emitted after those of further (more general) ones with the
+    same priority, so they win among those; across priorities, the higher
+    priority wins whichever config declared it (by default, `warn` < `deny`
+    < `allow` < `forbid`). A config may set `inherit = false` to stop the
+    ascend there, ignoring any configs further up.
+
+    Each config may define `warn`, `deny`, `allow` and `forbid` arrays of
+    lint names, which are passed to `clippy` as the matching `-W`/`-D`/`-A`/
+    `-F` flags. Entries may be a bare lint name, a `@group_name` reference
+    into a `[groups]` table, or a `{{ lint = \"...\", priority = ..., reason = \"...\" }}`
+    table.",
+		env!("CARGO_PKG_VERSION")
+	);
+}
+
+/// Prints version information
+fn print_version() {
+	println!("cargo-clippy-lints {}", env!("CARGO_PKG_VERSION"));
+}
+
+fn main() -> Result<(), anyhow::Error> {
+	// Get our own arguments, skipping over the `clippy-lints` argument cargo
+	// re-invokes us with
+	// Note: When running with cargo, we're run with `clippy-lints` in the 2nd argument
 	let get_args = || std::env::args_os();
-	let status = match get_args().nth(1) {
-		// If we were run with `cargo`, skip the next argument (which will be our filename)
-		// Note: When running with cargo, we're run with `clippy-lints` in the 2nd argument
-		Some(arg) if arg == "clippy-lints" => lints.run_clippy(get_args().skip(2))?,
-		_ => lints.run_clippy(get_args().skip(1))?,
+	let our_args: Vec<_> = match get_args().nth(1) {
+		Some(arg) if arg == "clippy-lints" => get_args().skip(2).collect(),
+		_ => get_args().skip(1).collect(),
 	};
 
+	// If `--help`/`-h` or `--version`/`-V` were passed before the `--` separator,
+	// handle them ourselves instead of forwarding to clippy
+	for arg in our_args.iter().take_while(|arg| *arg != "--") {
+		match arg.to_str() {
+			Some("--help" | "-h") => {
+				print_help();
+				return Ok(());
+			},
+			Some("--version" | "-V") => {
+				print_version();
+				return Ok(());
+			},
+			_ => {},
+		}
+	}
+
+	// Get the lints, merged from every `lints.toml` up the directory hierarchy
+	let lints = Lints::from_config_merged()?;
+
+	// Then run clippy
+	let status = lints.run_clippy(our_args)?;
+
 	// And check the status
 	match status {
 		status if status.success() => Ok(()),
 		_ => anyhow::bail!("Clippy returned non-0 status: {}", status),
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Helper to build a `Vec<OsString>` from string literals
+	fn os_args(args: &[&str]) -> Vec<OsString> {
+		args.iter().map(OsString::from).collect()
+	}
+
+	#[test]
+	fn split_args_no_separator() {
+		let (check_args, clippy_args) = Lints::split_args(os_args(&["--frozen", "--offline"]));
+
+		assert_eq!(check_args, os_args(&["--frozen", "--offline"]));
+		assert_eq!(clippy_args, os_args(&[]));
+	}
+
+	#[test]
+	fn split_args_one_separator() {
+		let (check_args, clippy_args) = Lints::split_args(os_args(&["--frozen", "--", "-W", "clippy::pedantic"]));
+
+		assert_eq!(check_args, os_args(&["--frozen"]));
+		assert_eq!(clippy_args, os_args(&["-W", "clippy::pedantic"]));
+	}
+
+	#[test]
+	fn split_args_separator_with_trailing_flags() {
+		let (check_args, clippy_args) = Lints::split_args(os_args(&["--", "-W", "clippy::pedantic", "--cap-lints", "warn"]));
+
+		assert_eq!(check_args, os_args(&[]));
+		assert_eq!(clippy_args, os_args(&["-W", "clippy::pedantic", "--cap-lints", "warn"]));
+	}
+
+	#[test]
+	fn all_flags_default_order_and_forbid() {
+		let lints = Lints {
+			warn: vec![LintEntry::Name("clippy::a".to_owned())],
+			deny: vec![LintEntry::Name("clippy::b".to_owned())],
+			allow: vec![LintEntry::Name("clippy::c".to_owned())],
+			forbid: vec![LintEntry::Name("clippy::d".to_owned())],
+			..Lints::default()
+		};
+
+		let flags = lints.all_flags().expect("no groups referenced");
+
+		assert_eq!(flags, vec!["-W", "clippy::a", "-D", "clippy::b", "-A", "clippy::c", "-F", "clippy::d"]);
+	}
+
+	#[test]
+	fn all_flags_explicit_priority_wins_across_levels() {
+		// An `allow` is emitted after a `deny` by default, but here the `deny`
+		// has an explicit priority higher than `allow`'s default: the `deny`
+		// must be emitted last, so it's the one clippy applies.
+		let lints = Lints {
+			deny: vec![LintEntry::Full {
+				lint:     "clippy::foo".to_owned(),
+				priority: Some(Level::Allow.default_priority() + 1),
+				reason:   None,
+			}],
+			allow: vec![LintEntry::Name("clippy::foo".to_owned())],
+			..Lints::default()
+		};
+
+		let flags = lints.all_flags().expect("no groups referenced");
+
+		assert_eq!(flags, vec!["-A", "clippy::foo", "-D", "clippy::foo"]);
+	}
+
+	#[test]
+	fn expand_entry_plain_lint() {
+		let lints = Lints::default();
+		let entry = LintEntry::Name("clippy::foo".to_owned());
+
+		let expanded = lints.expand_entry(Level::Warn, &entry).expect("not a group reference");
+
+		assert_eq!(expanded, vec![(Level::Warn.default_priority(), Level::Warn, "clippy::foo")]);
+	}
+
+	#[test]
+	fn expand_entry_expands_group() {
+		let lints = Lints {
+			groups: [("my_group".to_owned(), vec!["clippy::a".to_owned(), "clippy::b".to_owned()])].into(),
+			..Lints::default()
+		};
+		let entry = LintEntry::Name("@my_group".to_owned());
+
+		let expanded = lints.expand_entry(Level::Deny, &entry).expect("group is defined");
+
+		assert_eq!(expanded, vec![
+			(Level::Deny.default_priority(), Level::Deny, "clippy::a"),
+			(Level::Deny.default_priority(), Level::Deny, "clippy::b"),
+		]);
+	}
+
+	#[test]
+	fn expand_entry_unknown_group_errors() {
+		let lints = Lints::default();
+		let entry = LintEntry::Name("@missing".to_owned());
+
+		let result = lints.expand_entry(Level::Warn, &entry);
+
+		assert!(result.is_err());
+	}
+
+	#[test]
+	fn expand_entry_nested_group_errors() {
+		let lints = Lints {
+			groups: [("outer".to_owned(), vec!["@inner".to_owned()]), ("inner".to_owned(), vec![
+				"clippy::a".to_owned(),
+			])]
+			.into(),
+			..Lints::default()
+		};
+		let entry = LintEntry::Name("@outer".to_owned());
+
+		let result = lints.expand_entry(Level::Warn, &entry);
+
+		assert!(result.is_err());
+	}
+}